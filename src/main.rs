@@ -1,43 +1,261 @@
+use std::collections::HashMap;
 use std::num::NonZero;
+use std::num::NonZeroU64;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
 use image::{ImageBuffer, Rgba};
 use wgpu::{
-    wgc::instance, BindGroupLayoutDescriptor, BindGroupLayoutEntry, Buffer, ComputePipeline, ComputePipelineDescriptor, Device, Instance, InstanceDescriptor, PipelineCompilationOptions, PipelineLayoutDescriptor, Queue, RenderPipeline, RequestAdapterOptionsBase, Texture, TextureView
+    wgc::instance, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, Buffer, ComputePipeline, ComputePipelineDescriptor, Device, Instance, InstanceDescriptor, PipelineCompilationOptions, PipelineLayoutDescriptor, Queue, RequestAdapterOptionsBase
 };
 
 fn main() {
     pollster::block_on(async {
-        let gpu = Gpu::new().await;
+        let gpu = Gpu::new(ComputeJob::default()).await.expect("failed to initialize GPU");
+        println!("capabilities: {:?}", gpu.capabilities());
         gpu.run().await;
+
+        // Number-crunching demo: double a list of f32s on the GPU.
+        let input: Vec<f32> = (0..1024).map(|i| i as f32).collect();
+        let doubled = gpu.compute(&input, DOUBLE_SHADER, "main").await;
+        println!("compute[1] = {}", doubled[1]);
+
+        // Render path: draw a gradient and read it back to render.png.
+        gpu.render_to_image(256, 256).await;
+
+        // Recording engine: fill a buffer with a multi-command recording.
+        let mut engine = Engine::new().await;
+        let shader = engine.add_shader(ENGINE_SHADER, "compute_main");
+        let count = 256u32;
+        let size = (count * std::mem::size_of::<u32>() as u32) as u64;
+        let mut recording = Recording::new();
+        let buffer = recording.create_buffer(
+            size,
+            wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+        );
+        recording.upload(buffer, vec![0u8; size as usize]);
+        recording.dispatch(shader, (workgroup_count(count, 64), 1, 1), vec![buffer]);
+        recording.download(buffer);
+        let (outputs, timings) = engine.run_recording(&recording).await;
+        println!("engine produced {} bytes", outputs[0].len());
+        if let Some(t) = timings {
+            println!("dispatch {:?}, readback {:?}", t.dispatch, t.readback);
+        }
     });
 }
 
+/// Demo kernel for the recording `Engine`: writes each index, doubled.
+const ENGINE_SHADER: &str = r#"
+@group(0) @binding(0)
+var<storage, read_write> data: array<u32>;
+
+@compute @workgroup_size(64)
+fn compute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x < arrayLength(&data)) {
+        data[gid.x] = gid.x * 2u;
+    }
+}
+"#;
+
+/// Demo kernel for `Gpu::compute`: doubles every element in place.
+const DOUBLE_SHADER: &str = r#"
+@group(0) @binding(0)
+var<storage, read_write> data: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= arrayLength(&data)) {
+        return;
+    }
+    data[gid.x] = data[gid.x] * 2.0;
+}
+"#;
+
+/// Configuration for a compute job: the output dimensions, the workgroup
+/// size the kernel declares, and the WGSL source to run.
+///
+/// The WGSL is loaded at runtime via `create_shader_module` rather than
+/// baked in with `include_wgsl!`, so callers can supply their own kernels.
+pub struct ComputeJob {
+    pub width: u32,
+    pub height: u32,
+    pub workgroup_size: (u32, u32),
+    pub wgsl: String,
+    pub entry_point: String,
+}
+
+impl Default for ComputeJob {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            workgroup_size: (8, 8),
+            wgsl: COMPUTE_SHADER.to_string(),
+            entry_point: "compute_main".to_string(),
+        }
+    }
+}
+
+/// Invocations per workgroup assumed by `Gpu::compute`; kernels passed to it
+/// should declare `@workgroup_size(64)`.
+const COMPUTE_GROUP_SIZE: u32 = 64;
+
+/// Number of workgroups needed to cover `extent` invocations at `group`
+/// invocations each, rounding up so the final partial group isn't dropped.
+fn workgroup_count(extent: u32, group: u32) -> u32 {
+    extent.div_ceil(group)
+}
+
+/// Repack `data`, laid out with `padded_bytes_per_row` per row, into a
+/// tightly-packed buffer of `unpadded_bytes_per_row` per row.
+///
+/// `copy_texture_to_buffer` over-aligns each row to 256 bytes; this drops the
+/// trailing padding so the result can be handed straight to `ImageBuffer`.
+fn strip_row_padding(
+    data: &[u8],
+    unpadded_bytes_per_row: u32,
+    padded_bytes_per_row: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    for row in 0..height as usize {
+        let start = row * padded_bytes_per_row as usize;
+        let end = start + unpadded_bytes_per_row as usize;
+        pixels.extend_from_slice(&data[start..end]);
+    }
+    pixels
+}
+
+/// Default gradient kernel, equivalent to the original `shader.wgsl`.
+///
+/// The output dimensions are supplied at `@binding(1)` so the kernel works at
+/// whatever `width`/`height` the `ComputeJob` advertises, instead of assuming
+/// a fixed 256×256 grid.
+const COMPUTE_SHADER: &str = r#"
+struct Dims {
+    width: u32,
+    height: u32,
+};
+
+@group(0) @binding(0)
+var<storage, read_write> output: array<u32>;
+@group(0) @binding(1)
+var<uniform> dims: Dims;
+
+@compute @workgroup_size(8, 8)
+fn compute_main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    if (gid.x >= dims.width || gid.y >= dims.height) {
+        return;
+    }
+    let r = u32(f32(gid.x) / f32(dims.width) * 255.0);
+    let g = u32(f32(gid.y) / f32(dims.height) * 255.0);
+    output[gid.y * dims.width + gid.x] = 0xff000000u | (g << 8u) | r;
+}
+"#;
+
+/// Errors that can occur while bringing up a `Gpu`.
+#[derive(Debug)]
+pub enum GpuError {
+    /// No adapter matched the requested options.
+    NoAdapter,
+    /// The device could not be created even after downshifting capabilities.
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no suitable GPU adapter found"),
+            GpuError::RequestDevice(e) => write!(f, "could not create device: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for GpuError {}
+
+/// Capabilities negotiated with the adapter at device creation time.
+///
+/// Callers can inspect this before dispatching to know whether optional
+/// features (like timestamp queries) are available or whether the device was
+/// downshifted to a reduced configuration.
+#[derive(Clone, Copy, Debug)]
+pub struct GpuCapabilities {
+    pub timestamp_query: bool,
+    pub max_storage_buffer_binding_size: u32,
+    pub max_compute_workgroups_per_dimension: u32,
+}
+
 struct Gpu {
     instance: Instance,
     device: Device,
     queue: Queue,
     pipeline: ComputePipeline,
-    tex: Texture,
-    tex_view: TextureView,
+    storage_buffer: Buffer,
+    dims_buffer: Buffer,
     output_buffer: Buffer,
+    capabilities: GpuCapabilities,
+    job: ComputeJob,
 }
 
 impl Gpu {
-    pub async fn new() -> Self {
+    pub async fn new(job: ComputeJob) -> Result<Self, GpuError> {
         let instance = Instance::new(&InstanceDescriptor::default());
-    
+
         let adapter = instance
             .request_adapter(&RequestAdapterOptionsBase::default())
             .await
-            .unwrap();
-    
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
-            ..Default::default()
-        }).await.unwrap();
-    
-        let texture_size = 256u32;
-        let buffer_size = (texture_size * texture_size * std::mem::size_of::<u32>() as u32) as wgpu::BufferAddress;
-    
+            .map_err(|_| GpuError::NoAdapter)?;
+
+        // Negotiate only the features/limits we can actually use. We would
+        // like timestamp queries and the adapter's full storage-buffer and
+        // workgroup budgets, but none of these are guaranteed.
+        let adapter_features = adapter.features();
+        let adapter_limits = adapter.limits();
+
+        let timestamp_query = adapter_features.contains(wgpu::Features::TIMESTAMP_QUERY);
+        let mut required_features = wgpu::Features::empty();
+        if timestamp_query {
+            required_features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
+
+        let mut required_limits = wgpu::Limits::default();
+        required_limits.max_storage_buffer_binding_size = adapter_limits
+            .max_storage_buffer_binding_size
+            .max(required_limits.max_storage_buffer_binding_size);
+        required_limits.max_compute_workgroups_per_dimension = adapter_limits
+            .max_compute_workgroups_per_dimension
+            .max(required_limits.max_compute_workgroups_per_dimension);
+
+        // Try the negotiated configuration first; if the adapter rejects it,
+        // downshift to plain defaults so weaker adapters still get a device.
+        let (device, queue) = match adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: None,
+                required_features,
+                required_limits: required_limits.clone(),
+                ..Default::default()
+            })
+            .await
+        {
+            Ok(dq) => dq,
+            Err(_) => adapter
+                .request_device(&wgpu::DeviceDescriptor::default())
+                .await
+                .map_err(GpuError::RequestDevice)?,
+        };
+
+        let capabilities = GpuCapabilities {
+            timestamp_query: device.features().contains(wgpu::Features::TIMESTAMP_QUERY),
+            max_storage_buffer_binding_size: device.limits().max_storage_buffer_binding_size,
+            max_compute_workgroups_per_dimension: device
+                .limits()
+                .max_compute_workgroups_per_dimension,
+        };
+
+        let buffer_size = (job.width * job.height * std::mem::size_of::<u32>() as u32) as wgpu::BufferAddress;
+
         // Create storage buffer
         let storage_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Storage Buffer"),
@@ -45,7 +263,7 @@ impl Gpu {
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
-    
+
         // Output buffer for readback
         let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             size: buffer_size,
@@ -53,9 +271,21 @@ impl Gpu {
             label: Some("Output Buffer"),
             mapped_at_creation: false,
         });
-    
-        // Shader
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+
+        // Dimensions uniform, so the kernel can size itself from the job
+        let dims_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dimensions Uniform"),
+            size: (2 * std::mem::size_of::<u32>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&dims_buffer, 0, bytemuck::cast_slice(&[job.width, job.height]));
+
+        // Shader, loaded from the job config at runtime
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(job.wgsl.as_str().into()),
+        });
     
         // Create bind group layout for storage buffer
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -71,6 +301,16 @@ impl Gpu {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
     
@@ -85,62 +325,47 @@ impl Gpu {
         let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
             cache: None,
             compilation_options: PipelineCompilationOptions::default(),
-            entry_point: Some("compute_main"),
+            entry_point: Some(&job.entry_point),
             label: Some("Compute Pipeline"),
             layout: Some(&layout),
             module: &shader,
         });
     
-        // You don't need tex and tex_view if you're not using a render pipeline or sampling a texture
-        let dummy_tex = device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: 1,
-                height: 1,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::COPY_SRC,
-            label: None,
-            view_formats: &[],
-        });
-        let dummy_tex_view = dummy_tex.create_view(&Default::default());
-    
-        Self {
+        Ok(Self {
             instance,
             device,
             queue,
             pipeline,
-            tex: dummy_tex,
-            tex_view: dummy_tex_view,
+            storage_buffer,
+            dims_buffer,
             output_buffer,
-        }
+            capabilities,
+            job,
+        })
     }
-    
 
-    pub async fn run(&self) {
-        let texture_size = 256;
-        let buffer_size = (texture_size * texture_size * std::mem::size_of::<u32>()) as u64;
+    /// The capabilities negotiated with the adapter at creation time.
+    pub fn capabilities(&self) -> GpuCapabilities {
+        self.capabilities
+    }
 
-        // Create a buffer for the compute shader to write to
-        let storage_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Storage Buffer"),
-            size: buffer_size,
-            usage: wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_SRC
-                | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+
+    pub async fn run(&self) {
+        let buffer_size = (self.job.width * self.job.height * std::mem::size_of::<u32>() as u32) as u64;
 
         let bind_group_layout = self.pipeline.get_bind_group_layout(0);
         let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: storage_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.storage_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.dims_buffer.as_entire_binding(),
+                },
+            ],
             label: None,
         });
 
@@ -155,12 +380,15 @@ impl Gpu {
             });
             cpass.set_pipeline(&self.pipeline);
             cpass.set_bind_group(0, &bind_group, &[]);
-            cpass.dispatch_workgroups(32, 32, 1); // 256 / 8 = 32
+            // Round up so a non-multiple size still covers the last partial workgroup.
+            let groups_x = workgroup_count(self.job.width, self.job.workgroup_size.0);
+            let groups_y = workgroup_count(self.job.height, self.job.workgroup_size.1);
+            cpass.dispatch_workgroups(groups_x, groups_y, 1);
         }
 
         // Copy to output buffer
         encoder.copy_buffer_to_buffer(
-            &storage_buffer,
+            &self.storage_buffer,
             0,
             &self.output_buffer,
             0,
@@ -175,16 +403,701 @@ impl Gpu {
         buffer_slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
         self.device.poll(wgpu::PollType::Wait).unwrap();
         rx.receive().await.unwrap().unwrap();
-        
+
         {
             let data = buffer_slice.get_mapped_range();
 
             let image: ImageBuffer<Rgba<u8>, _> =
-                ImageBuffer::from_raw(texture_size as _, texture_size as _, data.to_vec()).unwrap();
+                ImageBuffer::from_raw(self.job.width, self.job.height, data.to_vec()).unwrap();
 
             image.save("gradient.png").unwrap();
             println!("Saved gradient.png");
         }
         self.output_buffer.unmap();
     }
+
+    /// Run a compute kernel over a typed array and read the results back.
+    ///
+    /// The input is uploaded as a read-write storage buffer at `@binding(0)`,
+    /// one workgroup of `COMPUTE_GROUP_SIZE` invocations is dispatched per
+    /// `COMPUTE_GROUP_SIZE` elements (so the kernel should declare a matching
+    /// `@workgroup_size`), and the buffer is mapped back and reinterpreted as
+    /// `Vec<T>`. This is the image-free specialization used for general
+    /// number-crunching over `f32`/`u32`/`i32` arrays.
+    pub async fn compute<T: bytemuck::Pod>(&self, input: &[T], wgsl: &str, entry: &str) -> Vec<T> {
+        // A zero-sized buffer is a wgpu validation error, so empty input has
+        // nothing to dispatch and round-trips to an empty result.
+        if input.is_empty() {
+            return Vec::new();
+        }
+
+        let bytes: &[u8] = bytemuck::cast_slice(input);
+        let buffer_size = bytes.len() as wgpu::BufferAddress;
+
+        let storage_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compute Input/Output"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.queue.write_buffer(&storage_buffer, 0, bytes);
+
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Compute Readback"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Compute Kernel"),
+            source: wgpu::ShaderSource::Wgsl(wgsl.into()),
+        });
+        let pipeline = self.device.create_compute_pipeline(&ComputePipelineDescriptor {
+            cache: None,
+            compilation_options: PipelineCompilationOptions::default(),
+            entry_point: Some(entry),
+            label: Some("Compute Kernel Pipeline"),
+            layout: None,
+            module: &shader,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: storage_buffer.as_entire_binding(),
+            }],
+            label: None,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Compute Kernel Encoder"),
+        });
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Compute Kernel Pass"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(&pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            let groups = workgroup_count(input.len() as u32, COMPUTE_GROUP_SIZE);
+            cpass.dispatch_workgroups(groups, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&storage_buffer, 0, &output_buffer, 0, buffer_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = output_buffer.slice(..);
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
+        self.device.poll(wgpu::PollType::Wait).unwrap();
+        rx.receive().await.unwrap().unwrap();
+
+        let result = {
+            let data = slice.get_mapped_range();
+            bytemuck::cast_slice::<u8, T>(&data).to_vec()
+        };
+        output_buffer.unmap();
+        result
+    }
+
+    /// Render a gradient into an `Rgba8UnormSrgb` texture with a render
+    /// pipeline, copy it back to the host, and save `render.png`.
+    ///
+    /// `copy_texture_to_buffer` requires each row to be a multiple of
+    /// `COPY_BYTES_PER_ROW_ALIGNMENT` (256), so the readback buffer is
+    /// over-allocated with padded rows and the padding is stripped before the
+    /// tightly-packed pixels are handed to `ImageBuffer`.
+    pub async fn render_to_image(&self, width: u32, height: u32) {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+
+        let shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Render Shader"),
+            source: wgpu::ShaderSource::Wgsl(RENDER_SHADER.into()),
+        });
+
+        let pipeline = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // Round `width * 4` up to the next multiple of 256.
+        let unpadded_bytes_per_row = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Render Readback"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            rpass.set_pipeline(&pipeline);
+            rpass.draw(0..3, 0..1);
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
+        self.device.poll(wgpu::PollType::Wait).unwrap();
+        rx.receive().await.unwrap().unwrap();
+
+        {
+            let data = slice.get_mapped_range();
+
+            // Strip the trailing per-row padding into a tightly-packed buffer.
+            let pixels = strip_row_padding(&data, unpadded_bytes_per_row, padded_bytes_per_row, height);
+
+            let image: ImageBuffer<Rgba<u8>, _> =
+                ImageBuffer::from_raw(width, height, pixels).unwrap();
+            image.save("render.png").unwrap();
+            println!("Saved render.png");
+        }
+        readback.unmap();
+    }
+}
+
+/// Fullscreen-triangle gradient used by `Gpu::render_to_image`.
+const RENDER_SHADER: &str = r#"
+struct VsOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> VsOut {
+    var corners = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 3.0, -1.0),
+        vec2<f32>(-1.0,  3.0),
+    );
+    var out: VsOut;
+    out.pos = vec4<f32>(corners[vi], 0.0, 1.0);
+    out.uv = corners[vi] * 0.5 + vec2<f32>(0.5, 0.5);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.uv, 0.0, 1.0);
+}
+"#;
+
+// ---------------------------------------------------------------------------
+// Recording-based command engine.
+//
+// Instead of hardcoding a single pipeline + dispatch + readback, work is
+// described as a `Recording` (a list of `Command`s) that the `Engine` walks
+// when it is run. Resources are referred to by lightweight `Id` handles that
+// are materialized lazily into real `Buffer`s while the recording executes.
+// ---------------------------------------------------------------------------
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Opaque handle to a resource referenced inside a `Recording`.
+///
+/// Ids are allocated from a process-wide atomic counter so they are unique
+/// across every recording and engine.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Id(NonZeroU64);
+
+impl Id {
+    fn next() -> Self {
+        let raw = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Id(NonZeroU64::new(raw).expect("id counter overflowed"))
+    }
+}
+
+/// Handle to a buffer created inside a recording.
+pub type BufId = Id;
+/// Handle to a shader registered on the engine.
+pub type ShaderId = Id;
+/// Handle to any resource bound into a dispatch.
+pub type ResourceId = Id;
+
+/// A single step in a `Recording`.
+pub enum Command {
+    /// Materialize a buffer of `size` bytes with the given usage flags.
+    CreateBuffer(BufId, u64, wgpu::BufferUsages),
+    /// Upload raw bytes into a previously created buffer.
+    Upload(BufId, Vec<u8>),
+    /// Run a compute shader, binding `bindings[i]` at binding slot `i`.
+    Dispatch {
+        shader: ShaderId,
+        wg: (u32, u32, u32),
+        bindings: Vec<ResourceId>,
+    },
+    /// Read a buffer back to the host; returned in recorded order from `run_recording`.
+    Download(BufId),
+}
+
+/// An ordered list of commands to execute on the GPU in a single submission.
+#[derive(Default)]
+pub struct Recording {
+    commands: Vec<Command>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocate a buffer handle and record its creation.
+    pub fn create_buffer(&mut self, size: u64, usage: wgpu::BufferUsages) -> BufId {
+        let id = Id::next();
+        self.commands.push(Command::CreateBuffer(id, size, usage));
+        id
+    }
+
+    /// Record an upload of `data` into `buf`.
+    pub fn upload(&mut self, buf: BufId, data: Vec<u8>) {
+        self.commands.push(Command::Upload(buf, data));
+    }
+
+    /// Record a compute dispatch.
+    pub fn dispatch(&mut self, shader: ShaderId, wg: (u32, u32, u32), bindings: Vec<ResourceId>) {
+        self.commands.push(Command::Dispatch { shader, wg, bindings });
+    }
+
+    /// Record a readback of `buf`.
+    pub fn download(&mut self, buf: BufId) {
+        self.commands.push(Command::Download(buf));
+    }
+}
+
+/// A registered compute shader: its pipeline and the layout its bind groups use.
+struct Shader {
+    id: ShaderId,
+    pipeline: ComputePipeline,
+    layout: BindGroupLayout,
+}
+
+/// Wall-clock-equivalent timings resolved from GPU timestamp queries.
+///
+/// Only produced when the adapter exposes `wgpu::Features::TIMESTAMP_QUERY`;
+/// `Engine::run_recording` returns `None` otherwise.
+#[derive(Clone, Copy, Debug)]
+pub struct Timings {
+    /// Time spent in the compute passes, from the first dispatch to the last.
+    pub dispatch: Duration,
+    /// Time spent copying the downloaded buffers into their readback buffers.
+    /// `Duration::ZERO` when the recording has no `Download` or the device
+    /// lacks `TIMESTAMP_QUERY_INSIDE_ENCODERS`, since no copy is timed then.
+    pub readback: Duration,
+}
+
+/// Timestamp-query scaffolding allocated once when the feature is available.
+struct TimestampQuery {
+    set: wgpu::QuerySet,
+    resolve: Buffer,
+    readback: Buffer,
+    period: f32,
+    /// Whether `CommandEncoder::write_timestamp` is usable (slots 2/3). It is
+    /// gated behind `TIMESTAMP_QUERY_INSIDE_ENCODERS`, separate from the
+    /// compute-pass timestamps (slots 0/1) that `TIMESTAMP_QUERY` authorizes.
+    encoder_timestamps: bool,
+}
+
+// Query slots written during a recording: 0/1 bracket the compute work,
+// 2/3 bracket the readback copies.
+const TIMESTAMP_COUNT: u32 = 4;
+
+/// A reusable, multi-pass compute engine driven by `Recording`s.
+pub struct Engine {
+    // Kept alive for the engine's lifetime so the device/queue it backs stay
+    // valid; not read again after construction.
+    #[allow(dead_code)]
+    instance: Instance,
+    device: Device,
+    queue: Queue,
+    shaders: Vec<Shader>,
+    timestamps: Option<TimestampQuery>,
+}
+
+impl Engine {
+    pub async fn new() -> Self {
+        let instance = Instance::new(&InstanceDescriptor::default());
+
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptionsBase::default())
+            .await
+            .unwrap();
+
+        // Request timestamp queries when the adapter supports them so
+        // recordings can be profiled; fall back silently otherwise.
+        // `TIMESTAMP_QUERY` only authorizes compute-pass timestamps; bracketing
+        // the readback via `write_timestamp` additionally needs the separate
+        // `TIMESTAMP_QUERY_INSIDE_ENCODERS` feature.
+        let adapter_features = adapter.features();
+        let has_timestamps = adapter_features.contains(wgpu::Features::TIMESTAMP_QUERY);
+        let has_encoder_timestamps = has_timestamps
+            && adapter_features.contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+        let mut required_features = wgpu::Features::empty();
+        if has_timestamps {
+            required_features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
+        if has_encoder_timestamps {
+            required_features |= wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+        }
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let timestamps = has_timestamps.then(|| {
+            let set = device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Engine Timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: TIMESTAMP_COUNT,
+            });
+            let resolve_size = (TIMESTAMP_COUNT as u64) * std::mem::size_of::<u64>() as u64;
+            let resolve = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Resolve"),
+                size: resolve_size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+            let readback = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp Readback"),
+                size: resolve_size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            TimestampQuery {
+                set,
+                resolve,
+                readback,
+                period: queue.get_timestamp_period(),
+                encoder_timestamps: has_encoder_timestamps,
+            }
+        });
+
+        Self {
+            instance,
+            device,
+            queue,
+            shaders: Vec::new(),
+            timestamps,
+        }
+    }
+
+    /// Register a WGSL compute shader and return a handle to it.
+    ///
+    /// The bind group layout is derived from the shader via the automatic
+    /// pipeline layout, so each dispatch's bindings are matched by slot.
+    pub fn add_shader(&mut self, wgsl: &str, entry_point: &str) -> ShaderId {
+        let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Engine Shader"),
+            source: wgpu::ShaderSource::Wgsl(wgsl.into()),
+        });
+
+        let pipeline = self.device.create_compute_pipeline(&ComputePipelineDescriptor {
+            cache: None,
+            compilation_options: PipelineCompilationOptions::default(),
+            entry_point: Some(entry_point),
+            label: Some("Engine Compute Pipeline"),
+            layout: None,
+            module: &module,
+        });
+
+        let layout = pipeline.get_bind_group_layout(0);
+        let id = Id::next();
+        self.shaders.push(Shader { id, pipeline, layout });
+        id
+    }
+
+    fn shader(&self, id: ShaderId) -> &Shader {
+        self.shaders
+            .iter()
+            .find(|s| s.id == id)
+            .expect("unknown shader id")
+    }
+
+    /// Walk `recording`, record a single command encoder, submit it, and
+    /// return the mapped bytes of every `Download` in recorded order.
+    ///
+    /// The returned `Timings` is `Some` only when the device was created with
+    /// `TIMESTAMP_QUERY` support.
+    pub async fn run_recording(&self, recording: &Recording) -> (Vec<Vec<u8>>, Option<Timings>) {
+        let mut binds: HashMap<Id, Buffer> = HashMap::new();
+        // (readback buffer, size) for each Download, in recorded order.
+        let mut readbacks: Vec<(Buffer, u64)> = Vec::new();
+
+        let dispatch_count = recording
+            .commands
+            .iter()
+            .filter(|c| matches!(c, Command::Dispatch { .. }))
+            .count();
+        let mut dispatch_idx = 0usize;
+        let mut readback_started = false;
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Recording Encoder"),
+        });
+
+        for command in &recording.commands {
+            match command {
+                Command::CreateBuffer(id, size, usage) => {
+                    let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Recording Buffer"),
+                        size: *size,
+                        usage: *usage,
+                        mapped_at_creation: false,
+                    });
+                    binds.insert(*id, buffer);
+                }
+                Command::Upload(id, data) => {
+                    let buffer = binds.get(id).expect("upload to uncreated buffer");
+                    self.queue.write_buffer(buffer, 0, data);
+                }
+                Command::Dispatch { shader, wg, bindings } => {
+                    let shader = self.shader(*shader);
+                    let entries: Vec<wgpu::BindGroupEntry> = bindings
+                        .iter()
+                        .enumerate()
+                        .map(|(slot, res)| wgpu::BindGroupEntry {
+                            binding: slot as u32,
+                            resource: binds
+                                .get(res)
+                                .expect("dispatch binds uncreated resource")
+                                .as_entire_binding(),
+                        })
+                        .collect();
+
+                    let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        layout: &shader.layout,
+                        entries: &entries,
+                        label: Some("Recording Bind Group"),
+                    });
+
+                    // Bracket the compute work: begin on the first dispatch's
+                    // pass, end on the last (may be the same single pass).
+                    // Middle passes bracket nothing, so they must carry a plain
+                    // `None` rather than an all-`None` `ComputePassTimestampWrites`,
+                    // which wgpu rejects.
+                    let begin = (dispatch_idx == 0).then_some(0);
+                    let end = (dispatch_idx == dispatch_count - 1).then_some(1);
+                    let timestamp_writes = match &self.timestamps {
+                        Some(ts) if begin.is_some() || end.is_some() => {
+                            Some(wgpu::ComputePassTimestampWrites {
+                                query_set: &ts.set,
+                                beginning_of_pass_write_index: begin,
+                                end_of_pass_write_index: end,
+                            })
+                        }
+                        _ => None,
+                    };
+
+                    let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("Recording Compute Pass"),
+                        timestamp_writes,
+                    });
+                    cpass.set_pipeline(&shader.pipeline);
+                    cpass.set_bind_group(0, &bind_group, &[]);
+                    cpass.dispatch_workgroups(wg.0, wg.1, wg.2);
+                    drop(cpass);
+                    dispatch_idx += 1;
+                }
+                Command::Download(id) => {
+                    if let Some(ts) = &self.timestamps {
+                        if ts.encoder_timestamps && !readback_started {
+                            encoder.write_timestamp(&ts.set, 2);
+                            readback_started = true;
+                        }
+                    }
+                    let source = binds.get(id).expect("download of uncreated buffer");
+                    let size = source.size();
+                    let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Recording Readback"),
+                        size,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    });
+                    encoder.copy_buffer_to_buffer(source, 0, &readback, 0, size);
+                    readbacks.push((readback, size));
+                }
+            }
+        }
+
+        // Close the readback bracket and resolve the query set into a buffer
+        // we can map after submission.
+        if let Some(ts) = &self.timestamps {
+            if readback_started {
+                encoder.write_timestamp(&ts.set, 3);
+            }
+            encoder.resolve_query_set(&ts.set, 0..TIMESTAMP_COUNT, &ts.resolve, 0);
+            encoder.copy_buffer_to_buffer(&ts.resolve, 0, &ts.readback, 0, ts.resolve.size());
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+
+        let mut results = Vec::with_capacity(readbacks.len());
+        for (readback, _size) in &readbacks {
+            let slice = readback.slice(..);
+            let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+            slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
+            self.device.poll(wgpu::PollType::Wait).unwrap();
+            rx.receive().await.unwrap().unwrap();
+            let data = slice.get_mapped_range().to_vec();
+            results.push(data);
+            readback.unmap();
+        }
+
+        let timings = match &self.timestamps {
+            Some(ts) if dispatch_count > 0 => {
+                let slice = ts.readback.slice(..);
+                let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+                slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
+                self.device.poll(wgpu::PollType::Wait).unwrap();
+                rx.receive().await.unwrap().unwrap();
+                let raw: Vec<u64> = {
+                    let data = slice.get_mapped_range();
+                    bytemuck::cast_slice::<u8, u64>(&data).to_vec()
+                };
+                ts.readback.unmap();
+
+                let to_duration = |begin: u64, end: u64| {
+                    let ticks = end.saturating_sub(begin);
+                    Duration::from_nanos((ticks as f64 * ts.period as f64) as u64)
+                };
+                Some(Timings {
+                    dispatch: to_duration(raw[0], raw[1]),
+                    readback: if readback_started {
+                        to_duration(raw[2], raw[3])
+                    } else {
+                        Duration::ZERO
+                    },
+                })
+            }
+            _ => None,
+        };
+
+        (results, timings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_row_padding_drops_trailing_bytes() {
+        // 2x2 RGBA image: 8 unpadded bytes/row padded out to 12.
+        let unpadded = 8u32;
+        let padded = 12u32;
+        let height = 2u32;
+        let mut data = Vec::new();
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]); // row 0 pixels
+        data.extend_from_slice(&[0, 0, 0, 0]); // row 0 padding
+        data.extend_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]); // row 1 pixels
+        data.extend_from_slice(&[0, 0, 0, 0]); // row 1 padding
+
+        let packed = strip_row_padding(&data, unpadded, padded, height);
+        assert_eq!(packed, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+    }
+
+    #[test]
+    fn workgroup_count_rounds_up() {
+        // Exact multiples and partial final groups both covered.
+        assert_eq!(workgroup_count(256, 8), 32);
+        assert_eq!(workgroup_count(255, 8), 32);
+        assert_eq!(workgroup_count(257, 8), 33);
+        assert_eq!(workgroup_count(1, 64), 1);
+        assert_eq!(workgroup_count(0, 64), 0);
+    }
+
+    #[test]
+    fn bytemuck_round_trips_f32() {
+        // Mirror the cast path in `Gpu::compute`: values -> bytes -> values.
+        let input: Vec<f32> = vec![0.0, 1.5, -2.25, 1024.0];
+        let bytes: &[u8] = bytemuck::cast_slice(&input);
+        assert_eq!(bytes.len(), input.len() * std::mem::size_of::<f32>());
+        let back: Vec<f32> = bytemuck::cast_slice::<u8, f32>(bytes).to_vec();
+        assert_eq!(input, back);
+    }
 }